@@ -19,7 +19,7 @@ use esp_idf_matter::matter::data_model::objects::{Dataver, Endpoint, HandlerComp
 use esp_idf_matter::matter::data_model::system_model::descriptor;
 use esp_idf_matter::matter::utils::init::InitMaybeUninit;
 use esp_idf_matter::matter::utils::select::Coalesce;
-use esp_idf_matter::netif::EspMatterNetif;
+use esp_idf_matter::netif::{EspMatterNetif, EspUdpBind};
 use esp_idf_matter::persist;
 use esp_idf_matter::stack::test_device::{TEST_BASIC_COMM_DATA, TEST_DEV_ATT, TEST_PID, TEST_VID};
 use esp_idf_matter::{init_async_io, EspEthMatterStack};
@@ -153,7 +153,7 @@ async fn matter() -> Result<(), anyhow::Error> {
         // we are using a Wifi STA, provide the Wifi netif here
         EspMatterNetif::new(wifi.wifi().sta_netif(), sysloop),
         // The Matter stack needs UDP sockets to communicate with other Matter devices
-        edge_nal_std::Stack::new(),
+        EspUdpBind::new(),
         // The Matter stack needs a persister to store its state
         // `EspPersist`+`EspKvBlobStore` saves to a user-supplied NVS partition
         // under namespace `esp-idf-matter`
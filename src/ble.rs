@@ -46,6 +46,9 @@ struct State {
     c2_cccd_handle: Option<Handle>,
     connections: rs_matter::utils::storage::Vec<Connection, MAX_CONNECTIONS>,
     response: GattResponse,
+    connections_accepted: u32,
+    connections_rejected: u32,
+    writes_received: u32,
 }
 
 impl State {
@@ -59,6 +62,9 @@ impl State {
             c2_cccd_handle: None,
             connections: rs_matter::utils::storage::Vec::new(),
             response: GattResponse::new(),
+            connections_accepted: 0,
+            connections_rejected: 0,
+            writes_received: 0,
         }
     }
 
@@ -71,6 +77,9 @@ impl State {
             c2_cccd_handle: None,
             connections <- rs_matter::utils::storage::Vec::init(),
             response <- gatt_response::init(),
+            connections_accepted: 0,
+            connections_rejected: 0,
+            writes_received: 0,
         })
     }
 }
@@ -98,6 +107,25 @@ impl IndBuffer {
     }
 }
 
+/// BLE GATT diagnostics of an `EspBtpGattPeripheral`, as returned by
+/// `EspBtpGattContext::diagnostics`.
+///
+/// The counters are cumulative since the creation of the context.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EspBtpGattDiagnostics {
+    /// Number of BLE peer connections accepted
+    pub connections_accepted: u32,
+    /// Number of BLE peer connections ignored because the maximum number of
+    /// BTP sessions was already reached
+    pub connections_rejected: u32,
+    /// Number of BTP writes received on the C1 characteristic
+    pub writes_received: u32,
+    /// Number of currently connected BLE peers
+    pub connected: usize,
+    /// Number of currently connected BLE peers subscribed to the C2 characteristic
+    pub subscribed: usize,
+}
+
 /// The `'static` state of the `EspBtpGattPeripheral` struct.
 /// Isolated as a separate struct to allow for `const fn` construction
 /// and static allocation.
@@ -129,6 +157,39 @@ impl EspBtpGattContext {
         })
     }
 
+    /// Return the GATT MTU negotiated with the BLE peer with the provided address,
+    /// or `None` if the peer is not connected, or if no MTU was negotiated (yet).
+    pub fn mtu(&self, address: BtAddr) -> Option<u16> {
+        self.state.lock(|state| {
+            state
+                .borrow()
+                .connections
+                .iter()
+                .find(|conn| conn.peer.addr() == address.0)
+                .and_then(|conn| conn.mtu)
+        })
+    }
+
+    /// Return a snapshot of the GATT diagnostics, e.g. for logging the BLE
+    /// health during commissioning.
+    pub fn diagnostics(&self) -> EspBtpGattDiagnostics {
+        self.state.lock(|state| {
+            let state = state.borrow();
+
+            EspBtpGattDiagnostics {
+                connections_accepted: state.connections_accepted,
+                connections_rejected: state.connections_rejected,
+                writes_received: state.writes_received,
+                connected: state.connections.len(),
+                subscribed: state
+                    .connections
+                    .iter()
+                    .filter(|conn| conn.subscribed)
+                    .count(),
+            }
+        })
+    }
+
     pub(crate) fn reset(&self) -> Result<(), EspError> {
         self.state.lock(|state| {
             let mut state = state.borrow_mut();
@@ -669,7 +730,7 @@ where
     }
 
     fn register_conn_mtu(&self, conn_id: ConnectionId, mtu: u16) -> Result<(), EspError> {
-        self.ctx.state.lock(|state| {
+        let peer = self.ctx.state.lock(|state| {
             let mut state = state.borrow_mut();
             if let Some(conn) = state
                 .connections
@@ -677,9 +738,17 @@ where
                 .find(|conn| conn.conn_id == conn_id)
             {
                 conn.mtu = Some(mtu);
+
+                Some(conn.peer)
+            } else {
+                None
             }
         });
 
+        if let Some(peer) = peer {
+            info!("GATT MTU {mtu} negotiated with {peer}");
+        }
+
         Ok(())
     }
 
@@ -697,9 +766,12 @@ where
                     })
                     .map_err(|_| ())
                     .unwrap();
+                state.connections_accepted = state.connections_accepted.wrapping_add(1);
 
                 true
             } else {
+                state.connections_rejected = state.connections_rejected.wrapping_add(1);
+
                 false
             }
         });
@@ -750,6 +822,7 @@ where
     {
         let event = self.ctx.state.lock(|state| {
             let mut state = state.borrow_mut();
+            let state = &mut *state;
             let c1_handle = state.c1_handle;
             let c2_cccd_handle = state.c2_cccd_handle;
 
@@ -774,6 +847,8 @@ where
                     }
                 }
             } else if c1_handle == Some(handle) && offset == 0 {
+                state.writes_received = state.writes_received.wrapping_add(1);
+
                 return Some(GattPeripheralEvent::Write {
                     address: BtAddr(addr.into()),
                     data: value,
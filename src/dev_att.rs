@@ -0,0 +1,104 @@
+use esp_idf_svc::nvs::{EspNvs, EspNvsPartition, NvsPartitionId};
+use esp_idf_svc::sys::EspError;
+
+use log::{info, warn};
+
+use rs_matter::data_model::sdm::dev_att::{DataType, DevAttDataFetcher};
+use rs_matter::error::{Error, ErrorCode};
+
+use crate::error::to_persist_error;
+
+/// The NVS namespace used by `EspNvsDevAtt::new_default`.
+pub const DEFAULT_NAMESPACE: &str = "matter-dev-att";
+
+/// The NVS key of the Certification Declaration
+pub const CERT_DECLARATION_KEY: &str = "cd";
+/// The NVS key of the PAI certificate
+pub const PAI_KEY: &str = "pai";
+/// The NVS key of the DAC certificate
+pub const DAC_KEY: &str = "dac";
+/// The NVS key of the DAC public key
+pub const DAC_PUB_KEY_KEY: &str = "dac-pub";
+/// The NVS key of the DAC private key
+pub const DAC_PRIV_KEY_KEY: &str = "dac-priv";
+
+/// A `DevAttDataFetcher` implementation that loads the device attestation data
+/// (Certification Declaration, PAI and DAC certificates, and the DAC key pair)
+/// from an NVS namespace at runtime.
+///
+/// Unlike the hard-coded test attestation data, this allows each unit to have its own DAC,
+/// as provisioned during manufacturing - typically into a dedicated NVS partition,
+/// which is then not touched by the firmware.
+///
+/// As the DAC private key is a secret, that partition should be an encrypted one
+/// (i.e. `EspNvsPartition<NvsEncrypted>`, with NVS encryption enabled in the ESP-IDF config),
+/// rather than plaintext NVS, which can be read back from the flash of any unit.
+///
+/// Each item is stored as a raw BLOB (DER for the certificates, raw bytes for the keys)
+/// under the corresponding `*_KEY` key.
+///
+/// Since `MatterStack` expects a `&'static dyn DevAttDataFetcher`, the instance
+/// should be stored in a static cell.
+pub struct EspNvsDevAtt<T>(EspNvs<T>)
+where
+    T: NvsPartitionId;
+
+impl<T> EspNvsDevAtt<T>
+where
+    T: NvsPartitionId,
+{
+    /// Create a new instance that would load the attestation data from `DEFAULT_NAMESPACE`.
+    pub fn new_default(nvs: EspNvsPartition<T>) -> Result<Self, EspError> {
+        Self::new(nvs, DEFAULT_NAMESPACE)
+    }
+
+    /// Create a new instance.
+    ///
+    /// The namespace is opened read-only, and it must already exist.
+    ///
+    /// # Arguments
+    /// - `nvs`: The NVS partition where the attestation data is provisioned,
+    ///   preferably an encrypted one (see above).
+    /// - `namespace`: The namespace where the attestation data is provisioned.
+    pub fn new(nvs: EspNvsPartition<T>, namespace: &str) -> Result<Self, EspError> {
+        Ok(Self(EspNvs::new(nvs, namespace, false)?))
+    }
+
+    fn key(data_type: DataType) -> &'static str {
+        match data_type {
+            DataType::CertDeclaration => CERT_DECLARATION_KEY,
+            DataType::PAI => PAI_KEY,
+            DataType::DAC => DAC_KEY,
+            DataType::DACPubKey => DAC_PUB_KEY_KEY,
+            DataType::DACPrivKey => DAC_PRIV_KEY_KEY,
+        }
+    }
+}
+
+impl<T> DevAttDataFetcher for EspNvsDevAtt<T>
+where
+    T: NvsPartitionId,
+{
+    fn get_devatt_data(&self, data_type: DataType, data: &mut [u8]) -> Result<usize, Error> {
+        let key = Self::key(data_type);
+
+        let len = self
+            .0
+            .get_blob(key, data)
+            .map_err(|e| {
+                warn!("Dev att {key}: loading failed: {e:?}");
+                to_persist_error(e)
+            })?
+            .map(|data| data.len());
+
+        if let Some(len) = len {
+            info!("Dev att {key}: loaded {len} bytes");
+
+            Ok(len)
+        } else {
+            warn!("Dev att {key}: not provisioned");
+
+            Err(ErrorCode::NotFound.into())
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use core::fmt::Write;
+
+use esp_idf_svc::sys::{
+    esp, esp_efuse_mac_get_default, EspError, CONFIG_ESP_MAC_UNIVERSAL_MAC_ADDRESSES,
+};
+
+/// Return the base MAC address of the chip, as programmed in efuse by Espressif.
+///
+/// The base MAC is unique per chip and does not change across reboots or reflashes,
+/// so it is a good source for device identifiers which need to be unique
+/// on a network of devices running identical firmware images.
+pub fn base_mac() -> Result<[u8; 6], EspError> {
+    let mut mac = [0; 6];
+
+    esp!(unsafe { esp_efuse_mac_get_default(mac.as_mut_ptr()) })?;
+
+    Ok(mac)
+}
+
+/// Return a serial number unique to the chip, derived from its base MAC address
+/// (the MAC as 12 uppercase hex digits).
+///
+/// Suitable for the `serial_no` field of `BasicInfoConfig`.
+/// Since `BasicInfoConfig` usually needs to outlive the (statically allocated) Matter stack,
+/// the returned string should be stored in a static cell.
+pub fn serial_no() -> Result<heapless::String<12>, EspError> {
+    mac_hex()
+}
+
+/// Return a 12-bit commissioning discriminator derived from the chip's base MAC address.
+///
+/// The discriminator is stable across reboots and reflashes, so it can be pre-printed on the label.
+///
+/// Espressif assigns MAC addresses sequentially, but reserves several consecutive addresses
+/// per chip (2 or 4, depending on the chip - see `CONFIG_ESP_MAC_UNIVERSAL_MAC_ADDRESSES`),
+/// so the low bits of the base MAC are mostly the same across chips. Hence, the discriminator
+/// is the device-specific part of the MAC divided by the number of reserved addresses
+/// (modulo 4096), and chips from the same batch get distinct discriminators,
+/// for up to 4096 consecutive units.
+///
+/// Use with `persist::load_or_generate_comm_data_with_discriminator_default`
+/// or `persist::load_or_generate_comm_data_with_discriminator`.
+pub fn discriminator() -> Result<u16, EspError> {
+    let mac = base_mac()?;
+
+    let nic = u32::from_be_bytes([0, mac[3], mac[4], mac[5]]);
+    let unit = nic / (CONFIG_ESP_MAC_UNIVERSAL_MAC_ADDRESSES as u32).max(1);
+
+    Ok((unit & 0xfff) as u16)
+}
+
+/// Return an mDNS hostname unique to the chip, derived from its base MAC address.
+///
+/// As per the Matter Core spec (4.3.4), the hostname of an operational node is
+/// derived from its MAC address, formatted as 12 uppercase hex digits.
+/// It is stable across reboots and reflashes.
+pub fn hostname() -> Result<heapless::String<12>, EspError> {
+    mac_hex()
+}
+
+/// Return an mDNS host ID unique to the chip, derived from its base MAC address
+/// (the MAC, as a big-endian number).
+pub fn host_id() -> Result<u64, EspError> {
+    let mac = base_mac()?;
+
+    let mut id = [0; 8];
+    id[2..].copy_from_slice(&mac);
+
+    Ok(u64::from_be_bytes(id))
+}
+
+fn mac_hex() -> Result<heapless::String<12>, EspError> {
+    let mut hex = heapless::String::new();
+
+    for byte in base_mac()? {
+        write!(&mut hex, "{byte:02X}").unwrap();
+    }
+
+    Ok(hex)
+}
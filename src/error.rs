@@ -1,7 +1,14 @@
-use esp_idf_svc::sys::EspError;
+use core::sync::atomic::{AtomicI32, Ordering};
+
+use esp_idf_svc::sys::{
+    esp_err_t, EspError, ESP_ERR_NVS_NOT_ENOUGH_SPACE, ESP_ERR_NVS_NO_FREE_PAGES, ESP_OK,
+};
 
 use rs_matter::error::{Error, ErrorCode};
 
+/// The code of the last ESP-IDF error converted by `to_persist_error`, or `ESP_OK` if none
+static LAST_PERSIST_ERROR: AtomicI32 = AtomicI32::new(ESP_OK);
+
 /// Converts an ESP network error to an `rs-matter` error
 pub fn to_net_error(_err: EspError) -> Error {
     // TODO: The `rs-matter` error code is too generic
@@ -10,8 +17,83 @@ pub fn to_net_error(_err: EspError) -> Error {
 }
 
 /// Converts an ESP persistence error to an `rs-matter` error
-pub fn to_persist_error(_err: EspError) -> Error {
+///
+/// A full NVS partition is reported as `ErrorCode::ResourceExhausted`, so that the application
+/// can tell it apart from other persistence errors (e.g. by freeing up some space).
+/// The original error is kept and can be retrieved with `take_persist_error`.
+pub fn to_persist_error(err: EspError) -> Error {
+    LAST_PERSIST_ERROR.store(err.code(), Ordering::Relaxed);
+
+    let code = err.code();
+
+    if code == ESP_ERR_NVS_NOT_ENOUGH_SPACE as esp_err_t
+        || code == ESP_ERR_NVS_NO_FREE_PAGES as esp_err_t
+    {
+        return ErrorCode::ResourceExhausted.into();
+    }
+
     // TODO: The `rs-matter` error code is too generic
-    // TODO: Capture the backtrace and the original error
+    // TODO: Capture the backtrace
     ErrorCode::StdIoError.into()
 }
+
+/// Return and clear the ESP-IDF error last converted by `to_persist_error`, if any.
+///
+/// The error is also cleared by `EspKvBlobStore` after each successful write,
+/// so it is only returned while the NVS keeps failing.
+///
+/// Note that the returned error is the one of the most recent failing NVS operation
+/// (be it a store of the Matter state, or a load of the device attestation data),
+/// which is not necessarily the one behind the `rs-matter` error at hand.
+pub fn take_persist_error() -> Option<EspError> {
+    EspError::from(LAST_PERSIST_ERROR.swap(ESP_OK, Ordering::Relaxed))
+}
+
+/// Clear the ESP-IDF error recorded by `to_persist_error`, once an NVS write succeeds again
+pub(crate) fn clear_persist_error() {
+    LAST_PERSIST_ERROR.store(ESP_OK, Ordering::Relaxed);
+}
+
+/// Converts an ESP BLE error to an `rs-matter` error
+pub fn to_ble_error(_err: EspError) -> Error {
+    // TODO: Capture the backtrace and the original error
+    ErrorCode::BtpError.into()
+}
+
+/// The source of an error, as returned by `error_source`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ErrorSource {
+    /// The network interface or the Wifi driver (see `to_net_error`).
+    /// Usually transient, so retrying is a reasonable recovery.
+    Network,
+    /// The NVS persistence (see `to_persist_error`).
+    /// Use `take_persist_error` for the original ESP-IDF error.
+    Persistence,
+    /// The BLE stack (see `to_ble_error`).
+    Ble,
+    /// Any other error, typically originating from `rs-matter` itself.
+    Matter,
+}
+
+/// Classify an `rs-matter` error by its source, so that the application can decide how to recover.
+///
+/// Since all errors of this crate are funneled into `rs-matter` error codes, the classification
+/// is based on the codes produced by the `to_*_error` functions, and is therefore best-effort.
+///
+/// In particular, `rs-matter` returns `ErrorCode::StdIoError` for any I/O error
+/// (e.g. a failing UDP socket) and might return `ErrorCode::ResourceExhausted` as well,
+/// so these are only classified as `ErrorSource::Persistence` while an NVS error is pending,
+/// i.e. after an NVS operation failed, and before the next successful NVS write
+/// (or `take_persist_error`).
+pub fn error_source(err: &Error) -> ErrorSource {
+    let persist_error_pending = LAST_PERSIST_ERROR.load(Ordering::Relaxed) != ESP_OK;
+
+    match err.code() {
+        ErrorCode::NoNetworkInterface => ErrorSource::Network,
+        ErrorCode::StdIoError | ErrorCode::ResourceExhausted if persist_error_pending => {
+            ErrorSource::Persistence
+        }
+        ErrorCode::BtpError => ErrorSource::Ble,
+        _ => ErrorSource::Matter,
+    }
+}
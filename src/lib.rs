@@ -38,6 +38,9 @@ pub use wireless::*;
     not(esp32s2)
 ))]
 pub mod ble;
+#[cfg(esp_idf_comp_nvs_flash_enabled)]
+pub mod dev_att;
+pub mod device;
 pub mod error;
 #[cfg(all(feature = "std", feature = "rs-matter-stack"))]
 pub mod eth;
@@ -90,6 +93,48 @@ pub fn init_async_io() -> Result<(), esp_idf_svc::sys::EspError> {
     Ok(())
 }
 
+/// A utility function which runs a Matter application in a blocking fashion,
+/// for simple applications which do not otherwise need an async executor.
+///
+/// The function spawns a new thread with the provided stack size, initializes `async-io`
+/// in it (see `init_async_io`), and then drives the future returned by `f` to completion
+/// on that thread, returning its result.
+///
+/// The stack size should be generous: the futures of the Matter stack are large,
+/// and since they are not boxed, they live on the stack of the spawned thread.
+/// The examples use 75KB, which is a reasonable starting point. Pinning the large futures
+/// with `core::pin::pin!` inside `f` reduces the stack usage somewhat.
+///
+/// # Arguments
+/// - `stack_size`: The stack size (in bytes) of the thread running the future.
+/// - `f`: A closure returning the future to run. Called on the spawned thread,
+///   so the future itself does not need to be `Send`.
+#[cfg(feature = "std")]
+pub fn run_blocking<F, U, R, E>(stack_size: usize, f: F) -> Result<R, E>
+where
+    F: FnOnce() -> U + Send + 'static,
+    U: core::future::Future<Output = Result<R, E>>,
+    R: Send + 'static,
+    E: From<esp_idf_svc::sys::EspError> + Send + 'static,
+{
+    let thread = std::thread::Builder::new()
+        .stack_size(stack_size)
+        .spawn(move || {
+            // Eagerly initialize `async-io` to minimize the risk of stack blowups later on
+            init_async_io()?;
+
+            esp_idf_svc::hal::task::block_on(f())
+        })
+        .map_err(|_| {
+            esp_idf_svc::sys::EspError::from_infallible::<{ esp_idf_svc::sys::ESP_ERR_NO_MEM }>()
+        })?;
+
+    match thread.join() {
+        Ok(result) => result,
+        Err(panic) => std::panic::resume_unwind(panic),
+    }
+}
+
 #[inline(never)]
 #[cold]
 #[cfg(feature = "std")]
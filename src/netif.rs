@@ -17,7 +17,11 @@ use esp_idf_svc::eventloop::EspSystemEventLoop;
 use esp_idf_svc::hal::task::embassy_sync::EspRawMutex;
 use esp_idf_svc::handle::RawHandle;
 use esp_idf_svc::netif::{EspNetif, IpEvent};
-use esp_idf_svc::sys::{esp, esp_netif_get_ip6_linklocal, EspError, ESP_FAIL};
+use esp_idf_svc::sys::{
+    esp_ip6_addr_t, esp_netif_get_all_ip6, EspError, CONFIG_LWIP_IPV6_NUM_ADDRESSES, ESP_FAIL,
+};
+
+use log::warn;
 
 use rs_matter::utils::sync::Notification;
 use rs_matter_stack::netif::{Netif, NetifConf};
@@ -26,6 +30,42 @@ use crate::error::to_net_error;
 
 const TIMEOUT_PERIOD_SECS: u8 = 5;
 
+const MAX_IPV6_ADDRS: usize = CONFIG_LWIP_IPV6_NUM_ADDRESSES as _;
+
+const DEFAULT_BIND_RETRIES: u8 = 5;
+const DEFAULT_BIND_RETRY_DELAY_MS: u64 = 200;
+const MAX_BIND_RETRY_DELAY_MS: u64 = 5000;
+
+/// The scope of an IPv6 address assigned to a network interface
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Ipv6Scope {
+    /// A link-local address (`fe80::/10`), only reachable from the same link
+    LinkLocal,
+    /// A unique local address (`fc00::/7`), reachable within the site
+    /// (e.g. via a Thread border router)
+    UniqueLocal,
+    /// A global unicast address
+    Global,
+}
+
+impl Ipv6Scope {
+    /// Return the scope of the provided unicast address,
+    /// or `None` if the address is not a unicast address usable by the Matter stack.
+    pub fn of(addr: &Ipv6Addr) -> Option<Self> {
+        let segment = addr.segments()[0];
+
+        if addr.is_unspecified() || addr.is_loopback() || addr.is_multicast() {
+            None
+        } else if segment & 0xffc0 == 0xfe80 {
+            Some(Self::LinkLocal)
+        } else if segment & 0xfe00 == 0xfc00 {
+            Some(Self::UniqueLocal)
+        } else {
+            Some(Self::Global)
+        }
+    }
+}
+
 /// A `Netif` and `UdpBind` traits implementation via ESP-IDF
 pub struct EspMatterNetif<T> {
     netif: T,
@@ -50,7 +90,27 @@ where
     }
 
     /// Get the network interface configuration
+    ///
+    /// Fails if the netif is down, even if it still holds an IP address
+    /// (i.e. an unplugged Ethernet cable, or a lost Wifi association).
+    /// This way, a link flap is observed as a configuration change by the Matter stack,
+    /// which then restarts the transport instead of operating on stale sockets - even if
+    /// the same IP is assigned once the link is back.
+    ///
+    /// Note however that a link loss does not post an `IpEvent` while the IP lease is kept,
+    /// so such a flap is only observed by the periodic (5 seconds) poll in `wait_any_conf_change`.
+    /// A flap shorter than the poll period might therefore go unobserved.
+    ///
+    /// Of all IPv6 addresses assigned to the netif, the one with the widest scope is reported
+    /// (global, then unique local, then link-local), so that the device is advertised
+    /// with an address reachable by controllers on other links as well.
+    /// The link-local address is only used until a routable address is assigned (e.g. via SLAAC),
+    /// which is then observed as a configuration change.
     pub fn get_netif_conf(netif: &EspNetif) -> Result<NetifConf, EspError> {
+        if !netif.is_up()? {
+            return Err(EspError::from_infallible::<ESP_FAIL>());
+        }
+
         let ip_info = netif.get_ip_info()?;
 
         let ipv4: Ipv4Addr = ip_info.ip.octets().into();
@@ -58,29 +118,11 @@ where
             return Err(EspError::from_infallible::<ESP_FAIL>());
         }
 
-        let mut ipv6: esp_idf_svc::sys::esp_ip6_addr_t = Default::default();
-
-        esp!(unsafe { esp_netif_get_ip6_linklocal(netif.handle() as _, &mut ipv6) })?;
-
-        let ipv6: Ipv6Addr = [
-            ipv6.addr[0].to_le_bytes()[0],
-            ipv6.addr[0].to_le_bytes()[1],
-            ipv6.addr[0].to_le_bytes()[2],
-            ipv6.addr[0].to_le_bytes()[3],
-            ipv6.addr[1].to_le_bytes()[0],
-            ipv6.addr[1].to_le_bytes()[1],
-            ipv6.addr[1].to_le_bytes()[2],
-            ipv6.addr[1].to_le_bytes()[3],
-            ipv6.addr[2].to_le_bytes()[0],
-            ipv6.addr[2].to_le_bytes()[1],
-            ipv6.addr[2].to_le_bytes()[2],
-            ipv6.addr[2].to_le_bytes()[3],
-            ipv6.addr[3].to_le_bytes()[0],
-            ipv6.addr[3].to_le_bytes()[1],
-            ipv6.addr[3].to_le_bytes()[2],
-            ipv6.addr[3].to_le_bytes()[3],
-        ]
-        .into();
+        let ipv6 = Self::get_netif_ipv6_addrs(netif)?
+            .into_iter()
+            .max_by_key(|(_, scope)| *scope)
+            .map(|(addr, _)| addr)
+            .ok_or(EspError::from_infallible::<ESP_FAIL>())?;
 
         let interface = netif.get_index();
 
@@ -94,6 +136,21 @@ where
         })
     }
 
+    /// Get all (valid) unicast IPv6 addresses of the network interface, together with their scope
+    pub fn get_netif_ipv6_addrs(
+        netif: &EspNetif,
+    ) -> Result<heapless::Vec<(Ipv6Addr, Ipv6Scope), MAX_IPV6_ADDRS>, EspError> {
+        let mut addrs: [esp_ip6_addr_t; MAX_IPV6_ADDRS] = Default::default();
+
+        let len = unsafe { esp_netif_get_all_ip6(netif.handle() as _, addrs.as_mut_ptr()) };
+
+        Ok(addrs[..(len.max(0) as usize).min(MAX_IPV6_ADDRS)]
+            .iter()
+            .map(to_ipv6_addr)
+            .filter_map(|addr| Ipv6Scope::of(&addr).map(|scope| (addr, scope)))
+            .collect())
+    }
+
     /// Wait for any IP configuration change
     pub async fn wait_any_conf_change(sysloop: &EspSystemEventLoop) -> Result<(), EspError> {
         let notification = Arc::new(Notification::<EspRawMutex>::new());
@@ -115,6 +172,17 @@ where
     }
 }
 
+fn to_ipv6_addr(addr: &esp_ip6_addr_t) -> Ipv6Addr {
+    let mut octets = [0; 16];
+
+    // The address is kept in network byte order, and ESP chips are little-endian
+    for (chunk, word) in octets.chunks_exact_mut(4).zip(addr.addr.iter()) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+
+    octets.into()
+}
+
 impl<T> Netif for EspMatterNetif<T>
 where
     T: Borrow<EspNetif>,
@@ -143,6 +211,79 @@ where
         Self: 'b;
 
     async fn bind(&self, local: core::net::SocketAddr) -> Result<Self::Socket<'_>, Self::Error> {
-        Stack::new().bind(local).await
+        EspUdpBind::new().bind(local).await
+    }
+}
+
+/// A `UdpBind` trait implementation via the STD UDP sockets in ESP-IDF,
+/// which retries binding the socket on transient errors.
+///
+/// On a warm restart of the Matter transport, the ESP-IDF network stack
+/// might need a moment before it frees the previously bound port, hence the retries.
+#[derive(Debug, Clone)]
+pub struct EspUdpBind {
+    retries: u8,
+    delay_ms: u64,
+}
+
+impl EspUdpBind {
+    /// Create a new `EspUdpBind` instance with the default retry settings.
+    pub const fn new() -> Self {
+        Self::new_with_retries(DEFAULT_BIND_RETRIES, DEFAULT_BIND_RETRY_DELAY_MS)
+    }
+
+    /// Create a new `EspUdpBind` instance.
+    ///
+    /// # Arguments
+    /// - `retries`: How many times to retry the bind on a transient error before giving up.
+    ///   `0` disables the retries.
+    /// - `delay_ms`: The delay before the first retry. The delay is doubled on each subsequent retry.
+    ///   All delays (including the first one) are capped to 5 seconds.
+    pub const fn new_with_retries(retries: u8, delay_ms: u64) -> Self {
+        Self { retries, delay_ms }
+    }
+
+    fn is_transient(err: &io::Error) -> bool {
+        matches!(
+            err.kind(),
+            io::ErrorKind::AddrInUse | io::ErrorKind::AddrNotAvailable
+        )
+    }
+}
+
+impl Default for EspUdpBind {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UdpBind for EspUdpBind {
+    type Error = io::Error;
+    type Socket<'b>
+        = UdpSocket
+    where
+        Self: 'b;
+
+    async fn bind(&self, local: core::net::SocketAddr) -> Result<Self::Socket<'_>, Self::Error> {
+        let mut delay_ms = self.delay_ms.min(MAX_BIND_RETRY_DELAY_MS);
+        let mut attempt = 0;
+
+        loop {
+            match Stack::new().bind(local).await {
+                Err(e) if attempt < self.retries && Self::is_transient(&e) => {
+                    attempt += 1;
+
+                    warn!(
+                        "Binding UDP socket {local} failed: {e}, retrying in {delay_ms}ms ({attempt}/{})",
+                        self.retries
+                    );
+
+                    Timer::after(Duration::from_millis(delay_ms)).await;
+
+                    delay_ms = delay_ms.saturating_mul(2).min(MAX_BIND_RETRY_DELAY_MS);
+                }
+                other => break other,
+            }
+        }
     }
 }
@@ -1,20 +1,46 @@
-use esp_idf_svc::nvs::{EspNvs, EspNvsPartition, NvsPartitionId};
-use esp_idf_svc::sys::EspError;
+use core::sync::atomic::{AtomicU32, Ordering};
 
-use log::info;
+use alloc::ffi::CString;
+
+use esp_idf_svc::handle::RawHandle;
+use esp_idf_svc::nvs::{
+    EspDefaultNvsPartition, EspNvs, EspNvsPartition, NvsCustom, NvsDefault, NvsEncrypted,
+    NvsPartitionId,
+};
+use esp_idf_svc::sys::{
+    bootloader_random_disable, bootloader_random_enable, esp, esp_random, nvs_close, nvs_commit,
+    nvs_erase_all, nvs_handle_t, nvs_open, nvs_open_mode_t_NVS_READWRITE, EspError,
+    ESP_ERR_INVALID_ARG,
+};
+
+use log::{info, warn};
 
 use rs_matter::error::Error;
+use rs_matter::BasicCommData;
 
 use rs_matter_stack::network::{Embedding, Network};
 use rs_matter_stack::persist::{Key, KvBlobBuf, KvBlobStore, KvPersist};
 use rs_matter_stack::MatterStack;
 
-use crate::error::to_persist_error;
+use crate::error::{clear_persist_error, to_persist_error};
+
+/// The NVS namespace used by the `*_default` functions for persisting the Matter state.
+///
+/// Use `new` / `EspKvBlobStore::new` with a different namespace when the NVS partition
+/// is shared with other subsystems, or when running more than one Matter instance.
+pub const DEFAULT_NAMESPACE: &str = "esp-idf-matter";
+
+/// The NVS namespace used by `load_or_generate_comm_data_default`
+/// and `load_or_generate_comm_data_with_discriminator_default`.
+pub const DEFAULT_COMM_DATA_NAMESPACE: &str = "matter-comm";
+
+/// Number of NVS writes committed by all `EspKvBlobStore` instances
+static NVS_WRITES: AtomicU32 = AtomicU32::new(0);
 
 /// A type alias for a `KvPersist` instance that uses the ESP IDF NVS API
 pub type EspMatterPersist<'a, T, C> = KvPersist<'a, EspKvBlobStore<T>, C>;
 
-/// Create a new ESP-IDF Matter persist instance that would persist in namespace `esp-idf-matter`.
+/// Create a new ESP-IDF Matter persist instance that would persist in `DEFAULT_NAMESPACE`.
 ///
 /// # Arguments
 /// - `nvs`: The NVS partition to use for persisting data.
@@ -28,7 +54,7 @@ where
     N: Network<Embedding = KvBlobBuf<Q>>,
     Q: Embedding + 'static,
 {
-    new(nvs, "esp-idf-matter", stack)
+    new(nvs, DEFAULT_NAMESPACE, stack)
 }
 
 /// Create a new ESP-IDF Matter persist instance.
@@ -53,6 +79,311 @@ where
     ))
 }
 
+/// Remove all Matter state persisted in `DEFAULT_NAMESPACE` of the default NVS partition.
+///
+/// See `reset` for details.
+pub fn reset_default(nvs: EspDefaultNvsPartition) -> Result<(), EspError> {
+    reset(nvs, DEFAULT_NAMESPACE)
+}
+
+/// Remove all Matter state persisted in the provided namespace of the provided NVS partition.
+///
+/// This erases every key in the namespace - i.e. the fabrics (including their ACLs),
+/// the basic info settings and the stored wireless networks - so that the device
+/// re-enters commissioning mode on next boot.
+///
+/// Calling this function when nothing is persisted is a no-op.
+///
+/// Note that the in-memory state of a running Matter stack is not affected, so
+/// the device should be restarted afterwards.
+///
+/// # Arguments
+/// - `nvs`: The NVS partition where the data is persisted (default, custom or encrypted).
+/// - `namespace`: The namespace where the data is persisted.
+pub fn reset<T>(nvs: EspNvsPartition<T>, namespace: &str) -> Result<(), EspError>
+where
+    T: NvsErase,
+{
+    T::erase_all(nvs, namespace)?;
+
+    info!("Namespace {namespace}: all blobs removed");
+
+    Ok(())
+}
+
+/// The NVS partition types whose namespaces can be erased with `reset`.
+///
+/// Implemented for all partition types supported by `EspNvs`.
+pub trait NvsErase: NvsPartitionId + Sized {
+    /// Erase all keys in the provided namespace of the provided partition.
+    fn erase_all(nvs: EspNvsPartition<Self>, namespace: &str) -> Result<(), EspError>;
+}
+
+impl NvsErase for NvsDefault {
+    fn erase_all(_nvs: EspNvsPartition<Self>, namespace: &str) -> Result<(), EspError> {
+        // `EspNvs` does not expose its raw handle for the default partition,
+        // so open a separate handle to the namespace.
+        // NVS allows multiple open handles, so this works even while the Matter stack is running.
+
+        let c_namespace = CString::new(namespace)
+            .map_err(|_| EspError::from_infallible::<ESP_ERR_INVALID_ARG>())?;
+
+        let mut handle: nvs_handle_t = 0;
+
+        esp!(unsafe {
+            nvs_open(
+                c_namespace.as_ptr(),
+                nvs_open_mode_t_NVS_READWRITE,
+                &mut handle as *mut _,
+            )
+        })?;
+
+        let result = erase_all_raw(handle);
+
+        unsafe {
+            nvs_close(handle);
+        }
+
+        result
+    }
+}
+
+impl NvsErase for NvsCustom {
+    fn erase_all(nvs: EspNvsPartition<Self>, namespace: &str) -> Result<(), EspError> {
+        erase_all_raw(EspNvs::new(nvs, namespace, true)?.handle())
+    }
+}
+
+impl NvsErase for NvsEncrypted {
+    fn erase_all(nvs: EspNvsPartition<Self>, namespace: &str) -> Result<(), EspError> {
+        erase_all_raw(EspNvs::new(nvs, namespace, true)?.handle())
+    }
+}
+
+fn erase_all_raw(handle: nvs_handle_t) -> Result<(), EspError> {
+    esp!(unsafe { nvs_erase_all(handle) })?;
+    esp!(unsafe { nvs_commit(handle) })
+}
+
+const COMM_DATA_KEY: &str = "comm-data";
+const COMM_DATA_LEN: usize = 6;
+
+/// Load the commissioning data (passcode and discriminator) persisted in
+/// `DEFAULT_COMM_DATA_NAMESPACE`, or generate and persist new random commissioning data
+/// if there is none yet.
+///
+/// See `load_or_generate_comm_data` for details.
+pub fn load_or_generate_comm_data_default<T>(
+    nvs: EspNvsPartition<T>,
+) -> Result<BasicCommData, EspError>
+where
+    T: NvsPartitionId,
+{
+    load_or_generate_comm_data(nvs, DEFAULT_COMM_DATA_NAMESPACE)
+}
+
+/// Load the commissioning data (passcode and discriminator) persisted in the provided namespace,
+/// or generate and persist new random commissioning data if there is none yet.
+///
+/// Use this instead of a hard-coded `BasicCommData` so that every device gets its own
+/// passcode and discriminator on first boot, which then stay the same across reboots - and therefore
+/// the QR code / manual pairing code printed on the label stays valid.
+///
+/// The commissioning data should be kept in a namespace different from the one used for
+/// persisting the Matter state, so that a `reset` does not change the pairing code of the device.
+///
+/// The passcode is a secret, so when generating it, a true entropy source for the hardware RNG
+/// is temporarily enabled. As this is not allowed while Wifi or BLE is running,
+/// call this function early on boot, before starting the Matter stack.
+///
+/// # Arguments
+/// - `nvs`: The NVS partition to use for persisting the commissioning data.
+/// - `namespace`: The namespace to use for persisting the commissioning data.
+pub fn load_or_generate_comm_data<T>(
+    nvs: EspNvsPartition<T>,
+    namespace: &str,
+) -> Result<BasicCommData, EspError>
+where
+    T: NvsPartitionId,
+{
+    load_or_generate(nvs, namespace, None)
+}
+
+/// Load the commissioning data persisted in `DEFAULT_COMM_DATA_NAMESPACE`, or generate and persist
+/// new commissioning data with a random passcode and the provided discriminator.
+///
+/// See `load_or_generate_comm_data_with_discriminator` for details.
+pub fn load_or_generate_comm_data_with_discriminator_default<T>(
+    nvs: EspNvsPartition<T>,
+    discriminator: u16,
+) -> Result<BasicCommData, EspError>
+where
+    T: NvsPartitionId,
+{
+    load_or_generate_comm_data_with_discriminator(nvs, DEFAULT_COMM_DATA_NAMESPACE, discriminator)
+}
+
+/// Same as `load_or_generate_comm_data`, except that the discriminator is not random,
+/// but the provided one.
+///
+/// Useful for fleets where the discriminator should be deterministic (e.g. so that it can be
+/// pre-printed), in which case the discriminator derived from the chip's MAC address
+/// (`device::discriminator`) can be used, or an explicitly assigned one.
+///
+/// If the persisted commissioning data has a different discriminator, the discriminator
+/// is updated, while the passcode is preserved.
+///
+/// # Arguments
+/// - `nvs`: The NVS partition to use for persisting the commissioning data.
+/// - `namespace`: The namespace to use for persisting the commissioning data.
+/// - `discriminator`: The 12-bit discriminator to use.
+pub fn load_or_generate_comm_data_with_discriminator<T>(
+    nvs: EspNvsPartition<T>,
+    namespace: &str,
+    discriminator: u16,
+) -> Result<BasicCommData, EspError>
+where
+    T: NvsPartitionId,
+{
+    if discriminator > 0xfff {
+        return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+    }
+
+    load_or_generate(nvs, namespace, Some(discriminator))
+}
+
+fn load_or_generate<T>(
+    nvs: EspNvsPartition<T>,
+    namespace: &str,
+    discriminator: Option<u16>,
+) -> Result<BasicCommData, EspError>
+where
+    T: NvsPartitionId,
+{
+    let mut nvs = EspNvs::new(nvs, namespace, true)?;
+
+    let mut buf = [0; COMM_DATA_LEN];
+
+    let mut password = None;
+
+    // Check the length first, as `get_blob` fails (rather than returning `None`)
+    // when the stored BLOB does not fit in the buffer
+    let len = nvs.blob_len(COMM_DATA_KEY)?;
+
+    let stored = if len == Some(COMM_DATA_LEN) {
+        nvs.get_blob(COMM_DATA_KEY, &mut buf)?
+            .and_then(comm_data_from_bytes)
+    } else {
+        None
+    };
+
+    if let Some(comm_data) = stored {
+        if discriminator.is_none() || discriminator == Some(comm_data.discriminator) {
+            info!(
+                "Loaded commissioning data, discriminator: {}",
+                comm_data.discriminator
+            );
+
+            return Ok(comm_data);
+        }
+
+        info!("Discriminator changed, updating commissioning data");
+
+        password = Some(comm_data.password);
+    } else if len.is_some() {
+        warn!("Invalid commissioning data found, generating new one");
+    }
+
+    let comm_data = with_entropy(|| BasicCommData {
+        password: password.unwrap_or_else(generate_password),
+        discriminator: discriminator.unwrap_or_else(|| (unsafe { esp_random() } & 0xfff) as u16),
+    });
+
+    nvs.set_blob(COMM_DATA_KEY, &comm_data_to_bytes(&comm_data))?;
+
+    info!(
+        "Generated commissioning data, discriminator: {}",
+        comm_data.discriminator
+    );
+
+    Ok(comm_data)
+}
+
+fn comm_data_from_bytes(data: &[u8]) -> Option<BasicCommData> {
+    if data.len() != COMM_DATA_LEN {
+        return None;
+    }
+
+    let password = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+    let discriminator = u16::from_le_bytes([data[4], data[5]]);
+
+    (is_valid_password(password) && discriminator <= 0xfff).then_some(BasicCommData {
+        password,
+        discriminator,
+    })
+}
+
+fn comm_data_to_bytes(comm_data: &BasicCommData) -> [u8; COMM_DATA_LEN] {
+    let mut data = [0; COMM_DATA_LEN];
+
+    data[..4].copy_from_slice(&comm_data.password.to_le_bytes());
+    data[4..].copy_from_slice(&comm_data.discriminator.to_le_bytes());
+
+    data
+}
+
+/// Run `f` with the hardware RNG fed by a true entropy source.
+///
+/// The commissioning data is generated before the Matter stack (and therefore Wifi and BLE)
+/// is running, in which case `esp_random` is only pseudo-random, unless an entropy source
+/// is explicitly enabled.
+fn with_entropy<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    unsafe {
+        bootloader_random_enable();
+    }
+
+    let result = f();
+
+    unsafe {
+        bootloader_random_disable();
+    }
+
+    result
+}
+
+fn generate_password() -> u32 {
+    loop {
+        let password = unsafe { esp_random() } % 99_999_998 + 1;
+
+        if is_valid_password(password) {
+            break password;
+        }
+    }
+}
+
+/// Check the passcode against the rules in the Matter Core spec (5.1.7.1):
+/// it should be in the range 1..=99999998, and trivial values are not allowed.
+fn is_valid_password(password: u32) -> bool {
+    const INVALID: &[u32] = &[
+        11111111, 22222222, 33333333, 44444444, 55555555, 66666666, 77777777, 88888888, 12345678,
+        87654321,
+    ];
+
+    (1..=99_999_998).contains(&password) && !INVALID.contains(&password)
+}
+
+/// Return the number of NVS writes (BLOB stores and removals) committed by
+/// all `EspKvBlobStore` instances since boot.
+///
+/// Useful for detecting excessive flash wear, e.g. when a controller keeps changing
+/// persisted state at a high rate.
+pub fn nvs_writes() -> u32 {
+    NVS_WRITES.load(Ordering::Relaxed)
+}
+
 /// A `KvBlobStore`` implementation that uses the ESP IDF NVS API
 /// to store and load the BLOBs.
 ///
@@ -65,9 +396,9 @@ impl<T> EspKvBlobStore<T>
 where
     T: NvsPartitionId,
 {
-    /// Create a new KV BLOB store instance that would persist in namespace `esp-idf-matter`.
+    /// Create a new KV BLOB store instance that would persist in `DEFAULT_NAMESPACE`.
     pub fn new_default(nvs: EspNvsPartition<T>) -> Result<Self, EspError> {
-        Self::new(nvs, "esp-idf-matter")
+        Self::new(nvs, DEFAULT_NAMESPACE)
     }
 
     /// Create a new KV BLOB store instance.
@@ -103,9 +434,14 @@ where
         let len = cb(buf)?;
         let data = &buf[..len];
 
-        self.0
-            .set_blob(key.as_ref(), data)
-            .map_err(to_persist_error)?;
+        self.0.set_blob(key.as_ref(), data).map_err(|e| {
+            warn!("Blob {key}: storing {} bytes failed: {e:?}", data.len());
+            to_persist_error(e)
+        })?;
+
+        clear_persist_error();
+
+        NVS_WRITES.fetch_add(1, Ordering::Relaxed);
 
         info!("Blob {key}: stored {} bytes {data:?}", data.len());
 
@@ -115,9 +451,18 @@ where
     fn remove(&mut self, key: Key, _buf: &mut [u8]) -> Result<(), Error> {
         // TODO: Not really async
 
-        self.0.remove(key.as_ref()).map_err(to_persist_error)?;
+        let removed = self.0.remove(key.as_ref()).map_err(|e| {
+            warn!("Blob {key}: removing failed: {e:?}");
+            to_persist_error(e)
+        })?;
+
+        clear_persist_error();
+
+        if removed {
+            NVS_WRITES.fetch_add(1, Ordering::Relaxed);
 
-        info!("Blob {key}: removed");
+            info!("Blob {key}: removed");
+        }
 
         Ok(())
     }
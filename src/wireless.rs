@@ -4,6 +4,9 @@ use esp_idf_svc::hal::modem::BluetoothModemPeripheral;
 use esp_idf_svc::hal::peripheral::{Peripheral, PeripheralRef};
 use esp_idf_svc::hal::task::embassy_sync::EspRawMutex;
 use esp_idf_svc::nvs::EspDefaultNvsPartition;
+use esp_idf_svc::sys::{esp, esp_ble_gatt_set_local_mtu};
+
+use log::error;
 
 use rs_matter::error::Error;
 use rs_matter::tlv::{FromTLV, ToTLV};
@@ -15,6 +18,7 @@ use rs_matter_stack::wireless::traits::{Ble, BleTask, WirelessConfig, WirelessDa
 use rs_matter_stack::{MatterStack, WirelessBle};
 
 use crate::ble::{EspBtpGattContext, EspBtpGattPeripheral};
+use crate::error::to_ble_error;
 
 #[cfg(all(
     esp_idf_comp_openthread_enabled,
@@ -99,6 +103,7 @@ pub struct EspMatterBle<'a, 'd, T> {
     context: &'a EspBtpGattContext,
     modem: PeripheralRef<'d, T>,
     nvs: EspDefaultNvsPartition,
+    mtu: Option<u16>,
 }
 
 impl<'a, 'd, T> EspMatterBle<'a, 'd, T>
@@ -135,8 +140,20 @@ where
             context,
             modem,
             nvs,
+            mtu: None,
         }
     }
+
+    /// Set the preferred (local) GATT MTU which is offered to the peer during the MTU exchange.
+    ///
+    /// By default, the ESP-IDF default local MTU is used.
+    /// Some phones commission faster and more reliably with a larger MTU.
+    ///
+    /// The MTU negotiated with a peer can be retrieved with `EspBtpGattContext::mtu`.
+    pub fn with_preferred_mtu(mut self, mtu: u16) -> Self {
+        self.mtu = Some(mtu);
+        self
+    }
 }
 
 impl<T> Ble for EspMatterBle<'_, '_, T>
@@ -147,10 +164,23 @@ where
     where
         A: BleTask,
     {
-        let bt = BtDriver::new(&mut self.modem, Some(self.nvs.clone())).unwrap();
+        let bt = BtDriver::new(&mut self.modem, Some(self.nvs.clone())).map_err(|e| {
+            error!("Failed to initialize the BT driver: {e:?}");
+            to_ble_error(e)
+        })?;
+
+        if let Some(mtu) = self.mtu {
+            esp!(unsafe { esp_ble_gatt_set_local_mtu(mtu) }).map_err(|e| {
+                error!("Failed to set the preferred GATT MTU {mtu}: {e:?}");
+                to_ble_error(e)
+            })?;
+        }
 
-        let peripheral =
-            EspBtpGattPeripheral::<bt::Ble>::new(GATTS_APP_ID, bt, self.context).unwrap();
+        let peripheral = EspBtpGattPeripheral::<bt::Ble>::new(GATTS_APP_ID, bt, self.context)
+            .map_err(|e| {
+                error!("Failed to initialize the BTP GATT peripheral: {e:?}");
+                to_ble_error(e)
+            })?;
 
         task.run(peripheral).await
     }
@@ -182,10 +212,17 @@ mod thread {
 
 #[cfg(esp_idf_comp_esp_wifi_enabled)]
 mod wifi {
+    use core::cell::{Cell, RefCell};
+    use core::pin::pin;
+
     use alloc::sync::Arc;
 
+    use embassy_futures::select::{select, Either};
+
     use embassy_sync::mutex::Mutex;
 
+    use embassy_time::{Duration, Timer};
+
     use embedded_svc::wifi::asynch::Wifi as WifiSvc;
 
     use enumset::EnumSet;
@@ -196,23 +233,38 @@ mod wifi {
     use esp_idf_svc::hal::peripheral::{Peripheral, PeripheralRef};
     use esp_idf_svc::hal::task::embassy_sync::EspRawMutex;
     use esp_idf_svc::handle::RawHandle;
-    use esp_idf_svc::netif::EspNetif;
+    use esp_idf_svc::netif::{EspNetif, NetifConfiguration, NetifStack};
     use esp_idf_svc::nvs::EspDefaultNvsPartition;
-    use esp_idf_svc::sys::{esp, EspError};
+    use esp_idf_svc::sys::{
+        esp, esp_bt_controller_get_status,
+        esp_bt_controller_status_t_ESP_BT_CONTROLLER_STATUS_ENABLED, esp_wifi_set_country_code,
+        esp_wifi_set_max_tx_power, esp_wifi_set_ps, wifi_ps_type_t,
+        wifi_ps_type_t_WIFI_PS_MAX_MODEM, wifi_ps_type_t_WIFI_PS_MIN_MODEM,
+        wifi_ps_type_t_WIFI_PS_NONE, EspError, ESP_ERR_INVALID_STATE,
+    };
     use esp_idf_svc::timer::EspTaskTimerService;
-    use esp_idf_svc::wifi::{AccessPointInfo, AsyncWifi, Capability, Configuration, EspWifi};
+    use esp_idf_svc::wifi::{
+        AccessPointInfo, AsyncWifi, Capability, Configuration, EspWifi, WifiDriver,
+    };
+
+    use log::{error, warn};
 
     use rs_matter::error::Error;
+    use rs_matter::utils::select::Coalesce;
+    use rs_matter::utils::sync::{blocking, Notification};
 
     use rs_matter_stack::netif::{Netif, NetifConf};
     use rs_matter_stack::wireless::svc::SvcWifiController;
     use rs_matter_stack::wireless::traits::{Wifi, WifiData, Wireless, WirelessTask, NC};
 
     use crate::error::to_net_error;
-    use crate::netif::EspMatterNetif;
+    use crate::netif::{EspMatterNetif, EspUdpBind};
 
     use super::EspWirelessMatterStack;
 
+    const WIFI_INIT_RETRIES: u8 = 3;
+    const WIFI_INIT_RETRY_DELAY_MS: u64 = 500;
+
     /// A type alias for an ESP-IDF Matter stack running over Wifi (and BLE, during commissioning).
     pub type EspWifiMatterStack<'a, E> = EspWirelessMatterStack<'a, Wifi, E>;
 
@@ -226,18 +278,137 @@ mod wifi {
     /// Note that Alexa does not (yet) work with non-concurrent commissioning.
     pub type EspWifiNCMatterStack<'a, E> = EspWirelessMatterStack<'a, Wifi<NC>, E>;
 
+    /// The Wifi power save mode.
+    ///
+    /// Power save trades latency (and the reception of some broadcast/multicast frames)
+    /// for power consumption.
+    /// With aggressive power save, mDNS queries from controllers might be missed, making the device
+    /// temporarily undiscoverable, so `MaxModem` is only recommended for battery-powered devices.
+    ///
+    /// Note also that ESP-IDF does not allow `None` while Wifi and BLE are both active
+    /// (i.e. during concurrent commissioning). While the BT controller is enabled,
+    /// `EspSharedWifi::set_power_save` therefore fails with `ESP_ERR_INVALID_STATE`,
+    /// while `EspMatterWifi` defers `None` until the BT controller is disabled.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+    pub enum WifiPowerSave {
+        /// No power save
+        None,
+        /// Minimum modem power save: the station wakes up to receive a beacon every DTIM period.
+        /// This is the ESP-IDF default.
+        #[default]
+        MinModem,
+        /// Maximum modem power save: the station wakes up to receive a beacon
+        /// every listen interval.
+        MaxModem,
+    }
+
+    impl From<WifiPowerSave> for wifi_ps_type_t {
+        fn from(ps: WifiPowerSave) -> Self {
+            match ps {
+                WifiPowerSave::None => wifi_ps_type_t_WIFI_PS_NONE,
+                WifiPowerSave::MinModem => wifi_ps_type_t_WIFI_PS_MIN_MODEM,
+                WifiPowerSave::MaxModem => wifi_ps_type_t_WIFI_PS_MAX_MODEM,
+            }
+        }
+    }
+
     /// The relation between a network interface and a controller is slightly different
     /// in the ESP-IDF crates compared to what `rs-matter-stack` wants, hence we need this helper type.
     #[derive(Clone)]
-    pub struct EspSharedWifi<'a>(
-        Arc<Mutex<EspRawMutex, AsyncWifi<EspWifi<'a>>>>,
-        EspSystemEventLoop,
-    );
+    pub struct EspSharedWifi<'a> {
+        wifi: Arc<Mutex<EspRawMutex, AsyncWifi<EspWifi<'a>>>>,
+        sysloop: EspSystemEventLoop,
+        udp: EspUdpBind,
+        max_tx_power: Arc<blocking::Mutex<EspRawMutex, Cell<Option<i8>>>>,
+    }
 
     impl<'a> EspSharedWifi<'a> {
         /// Create a new instance of the `EspSharedWifi` type.
         pub fn new(wifi: AsyncWifi<EspWifi<'a>>, sysloop: EspSystemEventLoop) -> Self {
-            Self(Arc::new(Mutex::new(wifi)), sysloop)
+            Self {
+                wifi: Arc::new(Mutex::new(wifi)),
+                sysloop,
+                udp: EspUdpBind::new(),
+                max_tx_power: Arc::new(blocking::Mutex::new(Cell::new(None))),
+            }
+        }
+
+        /// Use custom UDP socket bind retry settings when running the Matter stack
+        /// over this driver (see the `Wireless` implementation).
+        pub fn with_udp_bind(mut self, udp: EspUdpBind) -> Self {
+            self.udp = udp;
+            self
+        }
+
+        /// Limit the maximum Wifi TX power each time the Wifi driver is started.
+        ///
+        /// # Arguments
+        /// - `power`: The maximum TX power, in units of 0.25 dBm (e.g. `80` is 20 dBm).
+        ///   The ESP-IDF range is 8..=84; the value is further capped by the country code.
+        pub fn with_max_tx_power(self, power: i8) -> Self {
+            self.max_tx_power.lock(|max| max.set(Some(power)));
+            self
+        }
+
+        /// Set the Wifi power save mode.
+        ///
+        /// Can be called at any time; the mode stays in effect until changed again.
+        /// Fails with `ESP_ERR_INVALID_STATE` for `WifiPowerSave::None` while the BT controller
+        /// is enabled, as ESP-IDF does not allow it then.
+        pub async fn set_power_save(&self, ps: WifiPowerSave) -> Result<(), EspError> {
+            let _wifi = self.wifi.lock().await;
+
+            set_power_save(ps)
+        }
+
+        /// Set the maximum Wifi TX power, in units of 0.25 dBm.
+        ///
+        /// The limit is applied immediately if the Wifi driver is started, and is re-applied
+        /// each time the driver is started afterwards (as ESP-IDF resets the TX power on start).
+        pub async fn set_max_tx_power(&self, power: i8) -> Result<(), EspError> {
+            let wifi = self.wifi.lock().await;
+
+            self.max_tx_power.lock(|max| max.set(Some(power)));
+
+            if wifi.is_started()? {
+                set_max_tx_power(power)?;
+            }
+
+            Ok(())
+        }
+
+        /// Return information about the AP the Wifi STA is connected to (SSID, BSSID, channel, RSSI),
+        /// together with the IP configuration of the STA network interface.
+        ///
+        /// Returns `None` if the STA is not connected, or if it is connected but has no IP yet.
+        ///
+        /// When the stack runs over `EspMatterWifi`, use `EspMatterWifiHandle::connection` instead.
+        pub async fn connection(&self) -> Result<Option<(AccessPointInfo, NetifConf)>, EspError> {
+            let mut wifi = self.wifi.lock().await;
+
+            if !wifi.is_connected()? {
+                return Ok(None);
+            }
+
+            let Ok(conf) = EspMatterNetif::<EspNetif>::get_netif_conf(wifi.wifi().sta_netif())
+            else {
+                return Ok(None);
+            };
+
+            let ap_info = wifi.wifi_mut().driver_mut().get_ap_info()?;
+
+            Ok(Some((ap_info, conf)))
+        }
+
+        /// Set the Wifi country (regulatory domain) code, e.g. `*b"US"` or `*b"01"` for "world safe mode".
+        ///
+        /// The country code determines the channels used for scanning and connecting, as well as the maximum TX power.
+        /// Can be called while the STA is connected, in which case the driver re-applies the new channel
+        /// and power limits on its own (possibly dropping the connection if the current channel is no longer allowed).
+        pub async fn set_country_code(&self, code: [u8; 2]) -> Result<(), EspError> {
+            let _wifi = self.wifi.lock().await;
+
+            set_country_code(code)
         }
     }
 
@@ -245,37 +416,43 @@ mod wifi {
         type Error = EspError;
 
         async fn get_capabilities(&self) -> Result<EnumSet<Capability>, Self::Error> {
-            let wifi = self.0.lock().await;
+            let wifi = self.wifi.lock().await;
 
             wifi.get_capabilities()
         }
 
         async fn get_configuration(&self) -> Result<Configuration, Self::Error> {
-            let wifi = self.0.lock().await;
+            let wifi = self.wifi.lock().await;
 
             wifi.get_configuration()
         }
 
         async fn set_configuration(&mut self, conf: &Configuration) -> Result<(), Self::Error> {
-            let mut wifi = self.0.lock().await;
+            let mut wifi = self.wifi.lock().await;
 
             wifi.set_configuration(conf)
         }
 
         async fn start(&mut self) -> Result<(), Self::Error> {
-            let mut wifi = self.0.lock().await;
+            let mut wifi = self.wifi.lock().await;
+
+            wifi.start().await?;
 
-            wifi.start().await
+            if let Some(power) = self.max_tx_power.lock(Cell::get) {
+                set_max_tx_power(power)?;
+            }
+
+            Ok(())
         }
 
         async fn stop(&mut self) -> Result<(), Self::Error> {
-            let mut wifi = self.0.lock().await;
+            let mut wifi = self.wifi.lock().await;
 
             wifi.stop().await
         }
 
         async fn connect(&mut self) -> Result<(), Self::Error> {
-            let mut wifi = self.0.lock().await;
+            let mut wifi = self.wifi.lock().await;
 
             wifi.connect().await?;
 
@@ -290,19 +467,19 @@ mod wifi {
         }
 
         async fn disconnect(&mut self) -> Result<(), Self::Error> {
-            let mut wifi = self.0.lock().await;
+            let mut wifi = self.wifi.lock().await;
 
             wifi.disconnect().await
         }
 
         async fn is_started(&self) -> Result<bool, Self::Error> {
-            let wifi = self.0.lock().await;
+            let wifi = self.wifi.lock().await;
 
             wifi.is_started()
         }
 
         async fn is_connected(&self) -> Result<bool, Self::Error> {
-            let wifi = self.0.lock().await;
+            let wifi = self.wifi.lock().await;
 
             wifi.is_connected()
         }
@@ -310,13 +487,13 @@ mod wifi {
         async fn scan_n<const N: usize>(
             &mut self,
         ) -> Result<(heapless::Vec<AccessPointInfo, N>, usize), Self::Error> {
-            let mut wifi = self.0.lock().await;
+            let mut wifi = self.wifi.lock().await;
 
             wifi.scan_n().await
         }
 
         async fn scan(&mut self) -> Result<alloc::vec::Vec<AccessPointInfo>, Self::Error> {
-            let mut wifi = self.0.lock().await;
+            let mut wifi = self.wifi.lock().await;
 
             wifi.scan().await
         }
@@ -324,9 +501,9 @@ mod wifi {
 
     impl Netif for EspSharedWifi<'_> {
         async fn get_conf(&self) -> Result<Option<NetifConf>, Error> {
-            let wifi = self.0.lock().await;
+            let wifi = self.wifi.lock().await;
 
-            EspMatterNetif::new(wifi.wifi().sta_netif(), self.1.clone())
+            EspMatterNetif::new(wifi.wifi().sta_netif(), self.sysloop.clone())
                 .get_conf()
                 .await
         }
@@ -335,7 +512,7 @@ mod wifi {
             // Wait on any conf change
             // We anyway cannot lock the wifi mutex here (would be a deadlock), so we just wait for the event
 
-            EspMatterNetif::<EspNetif>::wait_any_conf_change(&self.1)
+            EspMatterNetif::<EspNetif>::wait_any_conf_change(&self.sysloop)
                 .await
                 .map_err(to_net_error)?;
 
@@ -343,12 +520,225 @@ mod wifi {
         }
     }
 
+    /// A `Wireless` trait implementation over an already constructed and configured Wifi driver.
+    ///
+    /// Useful when the application needs to configure the Wifi driver in ways
+    /// `EspMatterWifi` does not support, as all settings of the driver are preserved.
+    ///
+    /// Note that - unlike with `EspMatterWifi` - the Wifi driver stays initialized even while
+    /// the stack is commissioning over BLE, so this is best used with the concurrent commissioning
+    /// mode (`EspWifiMatterStack`).
+    impl Wireless for EspSharedWifi<'_> {
+        type Data = WifiData;
+
+        async fn run<A>(&mut self, mut task: A) -> Result<(), Error>
+        where
+            A: WirelessTask<Data = Self::Data>,
+        {
+            task.run(
+                self.clone(),
+                self.udp.clone(),
+                SvcWifiController::new(self.clone()),
+            )
+            .await
+        }
+    }
+
+    /// A handle to the Wifi connection of an `EspMatterWifi` instance.
+    ///
+    /// `EspMatterWifi` creates its Wifi driver only while the Matter stack is running over Wifi
+    /// (and - in non-concurrent mode - drops it while commissioning over BLE), so the application
+    /// cannot hold on to the driver itself. Instead, it can obtain this handle with
+    /// `EspMatterWifi::handle` before handing `EspMatterWifi` over to the Matter stack,
+    /// and use it to query the connection and to change the Wifi settings at runtime.
+    ///
+    /// The handle never accesses the Wifi driver: `EspMatterWifi::run` publishes the connection
+    /// into the handle, and applies the settings changed via the handle.
+    #[derive(Clone)]
+    pub struct EspMatterWifiHandle {
+        state: Arc<blocking::Mutex<EspRawMutex, RefCell<HandleState>>>,
+        changed: Arc<Notification<EspRawMutex>>,
+    }
+
+    struct HandleState {
+        connection: Option<(AccessPointInfo, NetifConf)>,
+        settings: WifiSettings,
+    }
+
+    /// The settings applied each time the Wifi driver is initialized
+    #[derive(Default, Copy, Clone, PartialEq, Eq)]
+    struct WifiSettings {
+        country_code: Option<[u8; 2]>,
+        power_save: Option<WifiPowerSave>,
+        max_tx_power: Option<i8>,
+    }
+
+    impl EspMatterWifiHandle {
+        fn new() -> Self {
+            Self {
+                state: Arc::new(blocking::Mutex::new(RefCell::new(HandleState {
+                    connection: None,
+                    settings: WifiSettings::default(),
+                }))),
+                changed: Arc::new(Notification::new()),
+            }
+        }
+
+        /// Return information about the AP the Wifi STA is connected to, together with the
+        /// IP configuration of the STA network interface.
+        ///
+        /// Returns `None` if the Wifi driver is not running (i.e. while commissioning over BLE
+        /// in non-concurrent mode), or if the STA is not connected or has no IP yet.
+        ///
+        /// The information is refreshed by the running Matter stack on each IP configuration change,
+        /// and at least every 5 seconds (i.e. for the RSSI).
+        pub fn connection(&self) -> Option<(AccessPointInfo, NetifConf)> {
+            self.state.lock(|state| state.borrow().connection.clone())
+        }
+
+        /// Set the Wifi country (regulatory domain) code, e.g. `*b"US"`
+        /// or `*b"01"` for "world safe mode".
+        ///
+        /// If the Wifi driver is running, the code is applied by the Matter stack shortly afterwards
+        /// (see `EspSharedWifi::set_country_code` for the effect on an existing connection).
+        /// The code is also re-applied each time the Wifi driver is initialized afterwards.
+        pub fn set_country_code(&self, code: [u8; 2]) {
+            self.update(|settings| settings.country_code = Some(code));
+        }
+
+        /// Set the Wifi power save mode.
+        ///
+        /// If the Wifi driver is running, the mode is applied by the Matter stack shortly afterwards.
+        /// The mode is also re-applied each time the Wifi driver is initialized afterwards.
+        /// See `WifiPowerSave` for the tradeoffs.
+        ///
+        /// `WifiPowerSave::None` is deferred while the BT controller is enabled
+        /// (i.e. during concurrent commissioning), and applied once it is disabled.
+        pub fn set_power_save(&self, ps: WifiPowerSave) {
+            self.update(|settings| settings.power_save = Some(ps));
+        }
+
+        /// Limit the maximum Wifi TX power, in units of 0.25 dBm (e.g. `80` is 20 dBm).
+        ///
+        /// If the Wifi driver is running, the limit is applied by the Matter stack shortly afterwards.
+        /// The limit is also re-applied each time the Wifi driver is started afterwards.
+        pub fn set_max_tx_power(&self, power: i8) {
+            self.update(|settings| settings.max_tx_power = Some(power));
+        }
+
+        fn update<F>(&self, f: F)
+        where
+            F: FnOnce(&mut WifiSettings),
+        {
+            self.state.lock(|state| f(&mut state.borrow_mut().settings));
+            self.changed.notify();
+        }
+
+        fn settings(&self) -> WifiSettings {
+            self.state.lock(|state| state.borrow().settings)
+        }
+
+        fn publish(&self, connection: Option<(AccessPointInfo, NetifConf)>) {
+            self.state
+                .lock(|state| state.borrow_mut().connection = connection);
+        }
+
+        /// Publish the connection of the running Wifi driver on each IP configuration change,
+        /// and apply the settings changed via the handle (as well as the deferred ones).
+        ///
+        /// # Arguments
+        /// - `wifi`: The running Wifi driver.
+        /// - `applied`: The settings already applied to the driver.
+        async fn monitor(
+            &self,
+            wifi: &EspSharedWifi<'_>,
+            mut applied: WifiSettings,
+        ) -> Result<(), Error> {
+            loop {
+                Self::apply(wifi, &self.settings(), &mut applied).await;
+
+                let connection = wifi.connection().await.unwrap_or_else(|e| {
+                    warn!("Getting the Wifi connection failed: {e:?}");
+                    None
+                });
+
+                self.publish(connection);
+
+                let mut conf_change = pin!(EspMatterNetif::<EspNetif>::wait_any_conf_change(
+                    &wifi.sysloop
+                ));
+                let mut changed = pin!(self.changed.wait());
+
+                if let Either::First(result) = select(&mut conf_change, &mut changed).await {
+                    result.map_err(to_net_error)?;
+                }
+            }
+        }
+
+        /// Apply those of the settings which differ from the already applied ones,
+        /// and update `applied` accordingly.
+        ///
+        /// Disabling the power save is deferred while the BT controller is enabled,
+        /// i.e. it stays pending until a subsequent call after the BT controller is disabled.
+        /// Settings which fail to apply are only logged, as retrying them would not help.
+        async fn apply(
+            wifi: &EspSharedWifi<'_>,
+            settings: &WifiSettings,
+            applied: &mut WifiSettings,
+        ) {
+            if settings.country_code != applied.country_code {
+                if let Some(code) = settings.country_code {
+                    if let Err(e) = wifi.set_country_code(code).await {
+                        warn!("Setting the Wifi country code failed: {e:?}");
+                    }
+                }
+
+                applied.country_code = settings.country_code;
+            }
+
+            let ps_deferred = settings.power_save == Some(WifiPowerSave::None) && bt_enabled();
+
+            if settings.power_save != applied.power_save && !ps_deferred {
+                if let Some(ps) = settings.power_save {
+                    if let Err(e) = wifi.set_power_save(ps).await {
+                        warn!("Setting the Wifi power save mode failed: {e:?}");
+                    }
+                }
+
+                applied.power_save = settings.power_save;
+            }
+
+            if settings.max_tx_power != applied.max_tx_power {
+                if let Some(power) = settings.max_tx_power {
+                    if let Err(e) = wifi.set_max_tx_power(power).await {
+                        warn!("Setting the Wifi max TX power failed: {e:?}");
+                    }
+                }
+
+                applied.max_tx_power = settings.max_tx_power;
+            }
+        }
+    }
+
+    /// Clears the connection published to an `EspMatterWifiHandle` when dropped,
+    /// which also happens when `EspMatterWifi::run` is cancelled.
+    struct Published<'a>(&'a EspMatterWifiHandle);
+
+    impl Drop for Published<'_> {
+        fn drop(&mut self) {
+            self.0.publish(None);
+        }
+    }
+
     /// A `Wireless` trait implementation via ESP-IDF's Wifi modem
     pub struct EspMatterWifi<'d, T> {
         modem: PeripheralRef<'d, T>,
         sysloop: EspSystemEventLoop,
         timer: EspTaskTimerService,
         nvs: EspDefaultNvsPartition,
+        sta_netif_conf: Option<NetifConfiguration>,
+        udp: EspUdpBind,
+        handle: EspMatterWifiHandle,
     }
 
     impl<'d, T> EspMatterWifi<'d, T>
@@ -369,6 +759,83 @@ mod wifi {
                 sysloop,
                 timer,
                 nvs,
+                sta_netif_conf: None,
+                udp: EspUdpBind::new(),
+                handle: EspMatterWifiHandle::new(),
+            }
+        }
+
+        /// Return a handle to the Wifi connection, usable while the Matter stack is running.
+        ///
+        /// The handle should be obtained before `EspMatterWifi` is handed over to the Matter stack.
+        pub fn handle(&self) -> EspMatterWifiHandle {
+            self.handle.clone()
+        }
+
+        /// Use a custom configuration for the Wifi STA network interface.
+        ///
+        /// Useful when the device needs a static IPv4 configuration instead of DHCP,
+        /// in which case `ip_configuration` should be set to `ipv4::ClientConfiguration::Fixed`.
+        ///
+        /// Since the Matter stack reads the IP configuration from the netif, the mDNS records
+        /// would then be published with the static addresses.
+        pub fn with_sta_netif_conf(mut self, conf: NetifConfiguration) -> Self {
+            self.sta_netif_conf = Some(conf);
+            self
+        }
+
+        /// Use custom UDP socket bind retry settings.
+        pub fn with_udp_bind(mut self, udp: EspUdpBind) -> Self {
+            self.udp = udp;
+            self
+        }
+
+        /// Set the Wifi country (regulatory domain) code, e.g. `*b"US"`.
+        ///
+        /// The country code is applied each time the Wifi driver is initialized.
+        /// When not set, the ESP-IDF default ("world safe mode") is used, which is the most conservative
+        /// w.r.t. the channels and TX power.
+        ///
+        /// The country code can also be changed at runtime
+        /// with `EspMatterWifiHandle::set_country_code`.
+        pub fn with_country_code(self, code: [u8; 2]) -> Self {
+            self.handle
+                .update(|settings| settings.country_code = Some(code));
+            self
+        }
+
+        /// Set the Wifi power save mode, applied each time the Wifi driver is initialized.
+        ///
+        /// When not set, the ESP-IDF default (`WifiPowerSave::MinModem`) is used.
+        /// See `WifiPowerSave` for the tradeoffs.
+        ///
+        /// `WifiPowerSave::None` is deferred while the BT controller is enabled
+        /// (i.e. during concurrent commissioning), and applied once it is disabled.
+        ///
+        /// The mode can also be changed at runtime with `EspMatterWifiHandle::set_power_save`.
+        pub fn with_power_save(self, ps: WifiPowerSave) -> Self {
+            self.handle
+                .update(|settings| settings.power_save = Some(ps));
+            self
+        }
+
+        /// Limit the maximum Wifi TX power, in units of 0.25 dBm (e.g. `80` is 20 dBm).
+        ///
+        /// Reducing the TX power saves power on devices close to the AP,
+        /// at the expense of range. When not set, the maximum allowed by the country code is used.
+        ///
+        /// The limit can also be changed at runtime with `EspMatterWifiHandle::set_max_tx_power`.
+        pub fn with_max_tx_power(self, power: i8) -> Self {
+            self.handle
+                .update(|settings| settings.max_tx_power = Some(power));
+            self
+        }
+
+        fn sta_netif(conf: Option<&NetifConfiguration>) -> Result<EspNetif, EspError> {
+            if let Some(conf) = conf {
+                EspNetif::new_with_conf(conf)
+            } else {
+                EspNetif::new(NetifStack::Sta)
             }
         }
     }
@@ -383,11 +850,40 @@ mod wifi {
         where
             A: WirelessTask<Data = Self::Data>,
         {
-            let wifi = AsyncWifi::wrap(
-                EspWifi::new(
+            // Initializing the driver might fail transiently (i.e. the radio is still
+            // being released by the BLE stack), so retry a few times before giving up
+            let mut attempt = 0;
+
+            let driver = loop {
+                match WifiDriver::new(
                     &mut self.modem,
                     self.sysloop.clone(),
                     Some(self.nvs.clone()),
+                ) {
+                    Ok(driver) => break driver,
+                    Err(e) if attempt < WIFI_INIT_RETRIES => {
+                        attempt += 1;
+
+                        warn!(
+                            "Initializing the Wifi driver failed: {e:?}, retrying ({attempt}/{WIFI_INIT_RETRIES})"
+                        );
+
+                        Timer::after(Duration::from_millis(WIFI_INIT_RETRY_DELAY_MS)).await;
+                    }
+                    Err(e) => {
+                        error!("Initializing the Wifi driver failed: {e:?}, giving up");
+
+                        return Err(to_net_error(e));
+                    }
+                }
+            };
+
+            let wifi = AsyncWifi::wrap(
+                EspWifi::wrap_all(
+                    driver,
+                    Self::sta_netif(self.sta_netif_conf.as_ref()).map_err(to_net_error)?,
+                    #[cfg(esp_idf_esp_wifi_softap_support)]
+                    EspNetif::new(NetifStack::Ap).map_err(to_net_error)?,
                 )
                 .map_err(to_net_error)?,
                 self.sysloop.clone(),
@@ -397,12 +893,62 @@ mod wifi {
 
             let wifi = EspSharedWifi::new(wifi, self.sysloop.clone());
 
-            task.run(
+            let _published = Published(&self.handle);
+
+            // Read the settings and store the TX power limit (applied when the driver is started)
+            // in one go, so that a limit set concurrently via the handle is not overwritten
+            let settings = self.handle.state.lock(|state| {
+                let settings = state.borrow().settings;
+
+                wifi.max_tx_power.lock(|max| max.set(settings.max_tx_power));
+
+                settings
+            });
+
+            let mut applied = WifiSettings {
+                max_tx_power: settings.max_tx_power,
+                ..Default::default()
+            };
+
+            EspMatterWifiHandle::apply(&wifi, &settings, &mut applied).await;
+
+            let mut task = pin!(task.run(
                 wifi.clone(),
-                edge_nal_std::Stack::new(),
-                SvcWifiController::new(wifi),
-            )
-            .await
+                self.udp.clone(),
+                SvcWifiController::new(wifi.clone())
+            ));
+
+            // A setting changed via the handle in the meantime is applied by the monitor,
+            // as the handle notifies it of every change
+            let mut monitor = pin!(self.handle.monitor(&wifi, applied));
+
+            select(&mut task, &mut monitor).coalesce().await
         }
     }
+
+    fn set_country_code(code: [u8; 2]) -> Result<(), EspError> {
+        let code = [code[0], code[1], 0];
+
+        // Do not let the driver adopt the country code advertised by the AP (802.11d),
+        // as the code was explicitly configured
+        esp!(unsafe { esp_wifi_set_country_code(code.as_ptr() as _, false) })
+    }
+
+    fn set_power_save(ps: WifiPowerSave) -> Result<(), EspError> {
+        if ps == WifiPowerSave::None && bt_enabled() {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_STATE>());
+        }
+
+        esp!(unsafe { esp_wifi_set_ps(ps.into()) })
+    }
+
+    fn bt_enabled() -> bool {
+        let status = unsafe { esp_bt_controller_get_status() };
+
+        status == esp_bt_controller_status_t_ESP_BT_CONTROLLER_STATUS_ENABLED
+    }
+
+    fn set_max_tx_power(power: i8) -> Result<(), EspError> {
+        esp!(unsafe { esp_wifi_set_max_tx_power(power) })
+    }
 }